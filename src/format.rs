@@ -0,0 +1,160 @@
+//! A thin abstraction over `goblin`'s per-format section tables, so the cave
+//! scanner can walk PE, ELF and Mach-O executables uniformly instead of only
+//! understanding PE `.text`.
+
+use goblin::elf::section_header::{SHF_EXECINSTR, SHT_NOBITS};
+use goblin::mach::Mach;
+use goblin::pe::section_table::IMAGE_SCN_MEM_EXECUTE;
+use goblin::Object;
+
+/// `VM_PROT_EXECUTE` from `<mach/vm_prot.h>`; not re-exported by `goblin`.
+const VM_PROT_EXECUTE: i32 = 0x04;
+
+/// A named, file-backed range of an object, expressed uniformly across PE
+/// sections, ELF sections and Mach-O segment sections.
+#[derive(Clone, Debug)]
+pub struct SectionEntry {
+    pub name: String,
+    pub file_offset: usize,
+    pub raw_size: usize,
+    pub virtual_size: usize,
+    pub virtual_address: u64,
+    pub executable: bool,
+}
+
+/// A parsed executable image, in whichever of the formats `goblin` recognizes.
+pub enum Executable<'a> {
+    Pe(goblin::pe::PE<'a>),
+    Elf(goblin::elf::Elf<'a>),
+    MachO(goblin::mach::MachO<'a>),
+}
+
+impl<'a> Executable<'a> {
+    pub fn parse(buffer: &'a [u8]) -> anyhow::Result<Self> {
+        match Object::parse(buffer).map_err(|err| anyhow::anyhow!(err))? {
+            Object::PE(pe) => Ok(Executable::Pe(pe)),
+            Object::Elf(elf) => Ok(Executable::Elf(elf)),
+            Object::Mach(Mach::Binary(macho)) => Ok(Executable::MachO(macho)),
+            Object::Mach(Mach::Fat(fat)) => {
+                // Fat binaries bundle several architectures; scan the first one
+                // rather than require the caller to pick a slice up front.
+                let macho = fat.into_iter().next()
+                    .ok_or_else(|| anyhow::anyhow!("fat Mach-O archive has no architectures"))?
+                    .map_err(|err| anyhow::anyhow!(err))?;
+                Ok(Executable::MachO(macho))
+            },
+            Object::Archive(_) => anyhow::bail!("archives are not scannable executables"),
+            Object::Unknown(magic) => anyhow::bail!("unrecognized object format (magic = 0x{:x})", magic),
+            _ => anyhow::bail!("unsupported object format"),
+        }
+    }
+
+    pub fn format_name(&self) -> &'static str {
+        match self {
+            Executable::Pe(_) => "PE",
+            Executable::Elf(_) => "ELF",
+            Executable::MachO(_) => "Mach-O",
+        }
+    }
+
+    pub fn is_64(&self) -> bool {
+        match self {
+            Executable::Pe(pe) => pe.is_64,
+            Executable::Elf(elf) => elf.is_64,
+            Executable::MachO(macho) => macho.is_64,
+        }
+    }
+
+    /// Lists every section/segment-section in the image, tagged with whether
+    /// it is marked executable in its format's own terms.
+    pub fn section_entries(&self) -> anyhow::Result<Vec<SectionEntry>> {
+        match self {
+            Executable::Pe(pe) => Ok(pe.sections.iter().map(|section| SectionEntry {
+                name: section.name().unwrap_or("???").to_string(),
+                file_offset: section.pointer_to_raw_data as usize,
+                raw_size: section.size_of_raw_data as usize,
+                virtual_size: section.virtual_size as usize,
+                virtual_address: section.virtual_address as u64,
+                executable: pe_section_executable(section.characteristics),
+            }).collect()),
+
+            Executable::Elf(elf) => Ok(elf.section_headers.iter()
+                .filter(|header| header.sh_type != SHT_NOBITS)
+                .map(|header| SectionEntry {
+                    name: elf.shdr_strtab.get_at(header.sh_name).unwrap_or("???").to_string(),
+                    file_offset: header.sh_offset as usize,
+                    raw_size: header.sh_size as usize,
+                    virtual_size: header.sh_size as usize,
+                    virtual_address: header.sh_addr,
+                    executable: elf_section_executable(header.sh_flags),
+                }).collect()),
+
+            Executable::MachO(macho) => {
+                let mut entries = Vec::new();
+
+                for segment in &macho.segments {
+                    let segment_name = segment.name().unwrap_or("???");
+                    // A section's executability follows its owning segment's
+                    // protection, not its name: real binaries keep genuinely
+                    // executable code in sections besides `__TEXT,__text`
+                    // (e.g. `__stubs`, `__stub_helper`), and a name allowlist
+                    // silently misses all of them.
+                    let executable = macho_segment_executable(segment.initprot);
+
+                    for section_result in segment.sections()? {
+                        let (section, _data) = section_result?;
+                        let section_name = section.name().unwrap_or("???");
+
+                        entries.push(SectionEntry {
+                            name: format!("{},{}", segment_name, section_name),
+                            file_offset: section.offset as usize,
+                            raw_size: section.size as usize,
+                            virtual_size: section.size as usize,
+                            virtual_address: section.addr,
+                            executable,
+                        });
+                    }
+                }
+
+                Ok(entries)
+            },
+        }
+    }
+}
+
+fn pe_section_executable(characteristics: u32) -> bool {
+    characteristics & IMAGE_SCN_MEM_EXECUTE != 0
+}
+
+fn elf_section_executable(sh_flags: u64) -> bool {
+    sh_flags & SHF_EXECINSTR as u64 != 0
+}
+
+fn macho_segment_executable(initprot: i32) -> bool {
+    initprot & VM_PROT_EXECUTE != 0
+}
+
+#[cfg(test)]
+mod executable_flag_tests {
+    use super::*;
+
+    #[test]
+    fn pe_flags_execute_bit() {
+        assert!(pe_section_executable(IMAGE_SCN_MEM_EXECUTE));
+        assert!(pe_section_executable(IMAGE_SCN_MEM_EXECUTE | 0x4000_0000));
+        assert!(!pe_section_executable(0));
+    }
+
+    #[test]
+    fn elf_flags_execinstr_bit() {
+        assert!(elf_section_executable(SHF_EXECINSTR as u64));
+        assert!(!elf_section_executable(goblin::elf::section_header::SHF_WRITE as u64));
+    }
+
+    #[test]
+    fn macho_segment_honors_initprot_not_name() {
+        assert!(macho_segment_executable(VM_PROT_EXECUTE));
+        assert!(macho_segment_executable(0x1 | VM_PROT_EXECUTE));
+        assert!(!macho_segment_executable(0x1));
+    }
+}
@@ -0,0 +1,341 @@
+//! Library API for locating code caves — long runs of a filler byte — in PE,
+//! ELF and Mach-O images, on disk or inside a running process.
+//!
+//! The `movile` binary is a thin CLI wrapper around [`find_caves`] and
+//! [`find_caves_in_process`]; embed this crate directly to consume caves
+//! programmatically instead of scraping the CLI's text output.
+
+pub mod format;
+pub mod process;
+pub mod symbols;
+
+use format::{Executable, SectionEntry};
+use serde::Serialize;
+
+/// Options controlling a cave scan.
+#[derive(Clone, Debug)]
+pub struct ScanOptions {
+    /// Filler bytes to look for; a run of any one of these values is a candidate cave.
+    pub patterns: Vec<u8>,
+    /// Minimal run length to report, in bytes.
+    pub min_size: u64,
+    /// Scan every section instead of only those flagged executable.
+    pub all_sections: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions { patterns: vec![0xCC], min_size: 0, all_sections: false }
+    }
+}
+
+/// A single reported code cave.
+#[derive(Clone, Debug, Serialize)]
+pub struct Cave {
+    pub section: String,
+    pub file_offset: u64,
+    pub rva: Option<u64>,
+    pub length: u64,
+    pub filler_byte: u8,
+}
+
+/// Scans `image`, an in-memory PE/ELF/Mach-O file, for runs of `opts.patterns`.
+pub fn find_caves(image: &[u8], opts: &ScanOptions) -> anyhow::Result<Vec<Cave>> {
+    find_caves_with_progress(image, opts, |_| {})
+}
+
+/// Like [`find_caves`], but calls `on_progress` with the absolute offset into
+/// `image` each time the scan advances to a new match, so a caller can drive
+/// a progress indicator over a large image.
+pub fn find_caves_with_progress(image: &[u8], opts: &ScanOptions, mut on_progress: impl FnMut(u64)) -> anyhow::Result<Vec<Cave>> {
+    let executable = Executable::parse(image)?;
+
+    if !executable.is_64() {
+        anyhow::bail!("unsupported architecture: 32-bit");
+    }
+
+    let regions = build_regions(&executable, image.len(), opts.all_sections)?;
+
+    let mut caves = Vec::new();
+    for region in &regions {
+        let data = image.get(region.file_offset .. region.file_offset + region.length)
+            .ok_or_else(|| anyhow::anyhow!("region '{}' out of bounds", region.label))?;
+
+        let runs = scan_runs(data, &opts.patterns, opts.min_size, |pos| on_progress(region.file_offset as u64 + pos));
+        for (start, length, filler_byte) in runs {
+            caves.push(Cave {
+                section: region.label.clone(),
+                file_offset: (region.file_offset + start) as u64,
+                rva: region.rva_base.map(|base| base + start as u64),
+                length,
+                filler_byte,
+            });
+        }
+    }
+
+    Ok(caves)
+}
+
+/// Scans a running process's mapped executable regions for runs of `opts.patterns`.
+/// Caves are reported with `rva` relative to the lowest readable+executable
+/// mapping of the same module path (all anonymous mappings count as one
+/// module), which is the module's load base only when its first mapping is
+/// executable. `file_offset` is always 0, since the bytes are read from memory
+/// rather than from a file.
+pub fn find_caves_in_process(pid: u32, opts: &ScanOptions) -> anyhow::Result<Vec<Cave>> {
+    find_caves_in_process_with_progress(pid, opts, |_| {})
+}
+
+/// Like [`find_caves_in_process`], but calls `on_progress` with the absolute
+/// address (within the target process) each time the scan advances to a new match.
+pub fn find_caves_in_process_with_progress(pid: u32, opts: &ScanOptions, mut on_progress: impl FnMut(u64)) -> anyhow::Result<Vec<Cave>> {
+    let regions = process::enumerate_regions(pid)?;
+
+    let module_bases: std::collections::HashMap<&str, u64> = regions.iter()
+        .fold(std::collections::HashMap::new(), |mut bases, region| {
+            bases.entry(region.module.as_str())
+                .and_modify(|base| *base = (*base).min(region.base))
+                .or_insert(region.base);
+            bases
+        });
+
+    let mut caves = Vec::new();
+    for region in &regions {
+        let data = process::read_region(pid, region)?;
+        let module_base = module_bases[region.module.as_str()];
+
+        let runs = scan_runs(&data, &opts.patterns, opts.min_size, |pos| on_progress(region.base + pos));
+        for (start, length, filler_byte) in runs {
+            caves.push(Cave {
+                section: region.module.clone(),
+                file_offset: 0,
+                rva: Some((region.base + start as u64) - module_base),
+                length,
+                filler_byte,
+            });
+        }
+    }
+
+    Ok(caves)
+}
+
+/// A contiguous range of file bytes to scan for filler runs, with an optional
+/// mapping back to a virtual address when it belongs to a mapped section.
+#[derive(Clone, Debug)]
+struct Region {
+    label: String,
+    file_offset: usize,
+    length: usize,
+    rva_base: Option<u64>,
+}
+
+fn build_regions(executable: &Executable, buffer_len: usize, all_sections: bool) -> anyhow::Result<Vec<Region>> {
+    regions_from_sections(executable.section_entries()?, buffer_len, all_sections)
+}
+
+fn regions_from_sections(mut sections: Vec<SectionEntry>, buffer_len: usize, all_sections: bool) -> anyhow::Result<Vec<Region>> {
+    let mut regions = Vec::new();
+
+    sections.sort_by_key(|s| s.file_offset);
+
+    for (idx, section) in sections.iter().enumerate() {
+        let name = &section.name;
+        let is_candidate = all_sections || section.executable;
+
+        let raw_start = section.file_offset;
+        let raw_size = section.raw_size;
+        let virtual_size = section.virtual_size;
+
+        if is_candidate {
+            // Only the part of the raw data that is actually mapped gets an RVA;
+            // any trailing file padding beyond virtual_size is reported separately below.
+            let mapped_len = raw_size.min(virtual_size);
+            if mapped_len > 0 {
+                regions.push(Region {
+                    label: name.clone(),
+                    file_offset: raw_start,
+                    length: mapped_len,
+                    rva_base: Some(section.virtual_address),
+                });
+            }
+
+            if raw_size > virtual_size {
+                regions.push(Region {
+                    label: format!("{} (raw padding beyond virtual size)", name),
+                    file_offset: raw_start + virtual_size,
+                    length: raw_size - virtual_size,
+                    rva_base: None,
+                });
+            }
+        }
+
+        if let Some(next) = sections.get(idx + 1) {
+            let section_end = raw_start + raw_size;
+            let next_start = next.file_offset;
+
+            if next_start > section_end {
+                regions.push(Region {
+                    label: format!("gap after {}", name),
+                    file_offset: section_end,
+                    length: next_start - section_end,
+                    rva_base: None,
+                });
+            }
+        }
+    }
+
+    for region in &regions {
+        if region.file_offset + region.length > buffer_len {
+            anyhow::bail!("region '{}' extends past end of file", region.label);
+        }
+    }
+
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod region_tests {
+    use super::*;
+
+    fn section(name: &str, file_offset: usize, raw_size: usize, virtual_size: usize, virtual_address: u64, executable: bool) -> SectionEntry {
+        SectionEntry {
+            name: name.to_string(),
+            file_offset,
+            raw_size,
+            virtual_size,
+            virtual_address,
+            executable,
+        }
+    }
+
+    #[test]
+    fn zero_virtual_size_does_not_double_report() {
+        let sections = vec![section(".weird", 0x400, 0x100, 0, 0x1000, true)];
+        let regions = regions_from_sections(sections, 0x1000, false).unwrap();
+
+        // The whole raw span is unmapped padding; it must be reported exactly once, with no RVA.
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].file_offset, 0x400);
+        assert_eq!(regions[0].length, 0x100);
+        assert_eq!(regions[0].rva_base, None);
+    }
+
+    #[test]
+    fn mapped_and_padding_regions_do_not_overlap() {
+        let sections = vec![section(".text", 0x400, 0x100, 0x80, 0x1000, true)];
+        let regions = regions_from_sections(sections, 0x1000, false).unwrap();
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!((regions[0].file_offset, regions[0].length), (0x400, 0x80));
+        assert_eq!(regions[0].rva_base, Some(0x1000));
+        assert_eq!((regions[1].file_offset, regions[1].length), (0x480, 0x80));
+        assert_eq!(regions[1].rva_base, None);
+    }
+
+    #[test]
+    fn non_executable_sections_are_skipped_without_all_sections() {
+        let sections = vec![section(".data", 0x400, 0x100, 0x100, 0x1000, false)];
+        assert!(regions_from_sections(sections, 0x1000, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn inter_section_gap_is_reported() {
+        let sections = vec![
+            section(".text", 0x400, 0x100, 0x100, 0x1000, true),
+            section(".rdata", 0x600, 0x100, 0x100, 0x2000, true),
+        ];
+        let regions = regions_from_sections(sections, 0x1000, false).unwrap();
+
+        let gap = regions.iter().find(|r| r.label == "gap after .text").unwrap();
+        assert_eq!((gap.file_offset, gap.length), (0x500, 0x100));
+    }
+}
+
+/// Scans `data` for maximal runs of any byte in `patterns`, reporting those
+/// whose length meets `min_size`. Returns `(start, length, filler_byte)` tuples
+/// with offsets relative to the start of `data`.
+///
+/// Rather than walking every byte, this jumps straight to each occurrence of a
+/// filler byte with `memchr` and only then extends forward to find the end of
+/// its run, so stretches of non-filler code are skipped in bulk. Every maximal
+/// run is still reported exactly once. `on_progress` is called with the offset
+/// of each match found, so a caller can drive a progress indicator.
+fn scan_runs(data: &[u8], patterns: &[u8], min_size: u64, mut on_progress: impl FnMut(u64)) -> Vec<(usize, u64, u8)> {
+    let mut matches = Vec::new();
+
+    for &filler in patterns {
+        let mut pos = 0_usize;
+
+        while let Some(found) = memchr::memchr(filler, &data[pos..]) {
+            let start = pos + found;
+            on_progress(start as u64);
+
+            let mut end = start;
+            while end < data.len() && data[end] == filler {
+                end += 1;
+            }
+
+            let length = (end - start) as u64;
+            if length >= min_size {
+                matches.push((start, length, filler));
+            }
+
+            pos = end;
+        }
+    }
+
+    matches.sort_by_key(|&(start, _, _)| start);
+    matches
+}
+
+#[cfg(test)]
+mod scan_tests {
+    use super::scan_runs;
+
+    fn runs(data: &[u8], patterns: &[u8], min_size: u64) -> Vec<(usize, u64, u8)> {
+        scan_runs(data, patterns, min_size, |_| {})
+    }
+
+    #[test]
+    fn finds_single_run() {
+        let data = [0x90, 0x90, 0x90, 0x01, 0x02];
+        assert_eq!(runs(&data, &[0x90], 1), vec![(0, 3, 0x90)]);
+    }
+
+    #[test]
+    fn reports_each_maximal_run_exactly_once() {
+        let data = [0x01, 0xCC, 0xCC, 0x02, 0xCC, 0xCC, 0xCC, 0x03];
+        assert_eq!(runs(&data, &[0xCC], 1), vec![(1, 2, 0xCC), (4, 3, 0xCC)]);
+    }
+
+    #[test]
+    fn filters_runs_shorter_than_min_size() {
+        let data = [0xCC, 0xCC, 0x01, 0xCC, 0xCC, 0xCC];
+        assert_eq!(runs(&data, &[0xCC], 3), vec![(3, 3, 0xCC)]);
+    }
+
+    #[test]
+    fn tracks_multiple_distinct_filler_bytes_in_file_order() {
+        let data = [0x00, 0x00, 0x01, 0x90, 0x90, 0x90];
+        assert_eq!(runs(&data, &[0x00, 0x90], 1), vec![(0, 2, 0x00), (3, 3, 0x90)]);
+    }
+
+    #[test]
+    fn run_spanning_to_end_of_data_is_included() {
+        let data = [0x01, 0xCC, 0xCC, 0xCC];
+        assert_eq!(runs(&data, &[0xCC], 1), vec![(1, 3, 0xCC)]);
+    }
+
+    #[test]
+    fn empty_data_has_no_runs() {
+        assert_eq!(runs(&[], &[0xCC], 1), Vec::new());
+    }
+
+    #[test]
+    fn reports_progress_at_each_match() {
+        let data = [0x01, 0xCC, 0xCC, 0x02, 0xCC];
+        let mut positions = Vec::new();
+        scan_runs(&data, &[0xCC], 1, |pos| positions.push(pos));
+        assert_eq!(positions, vec![1, 4]);
+    }
+}
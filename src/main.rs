@@ -1,21 +1,40 @@
 use argh::FromArgs;
-use goblin::pe::{section_table::SectionTable, PE};
 use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use movile::{find_caves_with_progress, find_caves_in_process_with_progress, symbols::SymbolTable, Cave, ScanOptions};
+use serde::Serialize;
 use std::{io::{stdout, Write}, path::PathBuf, process::ExitCode};
 
 
-/// Utility for locating potential code caves in x64 PE files.
+/// Utility for locating potential code caves in x64 PE, ELF and Mach-O executables.
 #[derive(FromArgs, PartialEq, Debug)]
 struct Cli {
-    /// executable file to scan
+    /// executable file to scan; mutually exclusive with `--pid`
     #[argh(positional)]
-    input: PathBuf,
+    input: Option<PathBuf>,
+    /// scan a running process's mapped image instead of an on-disk file
+    #[argh(option)]
+    pid: Option<u32>,
     /// output list file, set empty for stdout
     #[argh(option, short = 'o')]
     output: Option<PathBuf>,
     /// minimal cave size to consider (in bytes)
     #[argh(option, short = 'm')]
     min_size: u64,
+    /// filler byte to scan for, as hex (e.g. `cc`, `00`, `90`); repeatable, defaults to `cc`
+    #[argh(option, short = 'p')]
+    pattern: Vec<String>,
+    /// scan every section instead of only those flagged executable
+    #[argh(switch)]
+    all_sections: bool,
+    /// path to a `.map` file to source symbols from, instead of the image's own export table
+    #[argh(option)]
+    symbols: Option<PathBuf>,
+    /// disable nearest-symbol annotation of reported caves
+    #[argh(switch)]
+    no_symbols: bool,
+    /// output format: `text` (default) or `json`
+    #[argh(option, default = "String::from(\"text\")")]
+    format: String,
 }
 
 
@@ -27,102 +46,162 @@ fn main() -> ExitCode {
     }
 }
 
+#[derive(Serialize)]
+struct Digest {
+    input: String,
+    sections: Vec<String>,
+    caves: Vec<Cave>,
+}
+
 fn main_internal(cli: &Cli) -> Result<(), anyhow::Error> {
-    con::info_kv("selected executable", cli.input.to_string_lossy());
+    let patterns = parse_patterns(&cli.pattern)?;
+
     con::info_kv("selected minimal size", HumanBytes(cli.min_size));
+    con::info_kv("selected patterns", patterns.iter().map(|b| format!("0x{:02x}", b)).collect::<Vec<_>>().join(", "));
+
+    let opts = ScanOptions { patterns, min_size: cli.min_size, all_sections: cli.all_sections };
+
+    match (&cli.input, cli.pid) {
+        (Some(_), Some(_)) => anyhow::bail!("pass either an input file or --pid, not both"),
+        (None, None) => anyhow::bail!("pass an input file or --pid"),
+        (None, Some(pid)) => scan_process(cli, pid, &opts),
+        (Some(input), None) => scan_file(cli, input, &opts),
+    }
+}
+
+fn scan_file(cli: &Cli, input: &PathBuf, opts: &ScanOptions) -> anyhow::Result<()> {
+    con::info_kv("selected executable", input.to_string_lossy());
 
-    let buffer = std::fs::read(&cli.input)
+    let buffer = std::fs::read(input)
         .inspect(|buf| con::info_kv("read executable", HumanBytes(buf.len() as u64)))
         .inspect_err(|err| con::error_kv("failed to read executable", err))?;
 
-    let executable = PE::parse(&buffer)
-        .inspect(|exe| con::info_kv("parsed executable", exe.name.unwrap_or("???")))
-        .inspect_err(|err| con::error_kv("failed to parse executable", err))
-        .map_err(|err| anyhow::anyhow!(err))?;
-
-    if !executable.is_64 {
-        con::error_kv("unsupported architecture", "32-bit");
-        anyhow::bail!("unsupported architecture");
-    }
+    let executable = movile::format::Executable::parse(&buffer)
+        .inspect(|exe| con::info_kv("parsed executable", exe.format_name()))
+        .inspect_err(|err| con::error_kv("failed to parse executable", err))?;
 
-    let section = find_text_section(&executable)
-        .inspect(|&sec| con::info_kv("found .text",
-            format!("pointer to raw data = 0x{:X}", sec.pointer_to_raw_data)))
-        .inspect_err(|_| con::info("failed to find .text"))?;
+    let sections = executable.section_entries()?.iter().map(|s| s.name.clone()).collect::<Vec<_>>();
+    let symbols = build_symbol_table(cli, Some(&executable))?;
 
-    let text = {
-        let offset = section.pointer_to_raw_data as usize;
-        let size = section.size_of_raw_data as usize;
-        buffer.get(offset .. offset + size).ok_or_else(|| anyhow::anyhow!("range out of bounds"))
-    }
-        .inspect_err(|err| con::error_kv("failed to get .text", err))?;
+    let progress_bar = progress_bar_for(buffer.len() as u64);
+    con::info_kv("scanning for filler runs", "");
+    let caves = find_caves_with_progress(&buffer, opts, |pos| progress_bar.set_position(pos))
+        .inspect_err(|err| con::error_kv("scan failed", err))?;
+    progress_bar.finish_and_clear();
 
-    // std::fs::write("./dbg-text.bin", text)?;
+    con::info_kv("scan completed", format!("{} match(es) on configured filler bytes", caves.len()));
 
-    let progress_style = ProgressStyle::with_template(
-        "[{percent_precise}%] {bar:40.cyan/cyan} {pos:>7}/{len:7} {msg}"
-    )?.progress_chars("##-");
-    let progress_bar = ProgressBar::new(text.len() as u64).with_style(progress_style);
+    write_output(cli, &symbols, caves, || Digest {
+        input: input.to_string_lossy().into_owned(),
+        sections: sections.clone(),
+        caves: Vec::new(),
+    })
+}
 
-    con::info_kv("scanning .text for int3 sequences",
-        format!("size of raw data = {}", HumanBytes(section.size_of_raw_data as u64)));
+fn scan_process(cli: &Cli, pid: u32, opts: &ScanOptions) -> anyhow::Result<()> {
+    con::info_kv("selected process", pid);
+
+    let symbols = build_symbol_table(cli, None)?;
+
+    let regions = movile::process::enumerate_regions(pid)?;
+    let total_len: u64 = regions.iter().map(|r| r.size).sum();
+    let progress_bar = progress_bar_for(total_len);
+    con::info_kv("scanning process for filler runs", "");
+    // Progress arrives as absolute addresses; map each onto bytes scanned so far.
+    let caves = find_caves_in_process_with_progress(pid, opts, |addr| {
+        let mut scanned = 0;
+        for region in &regions {
+            if (region.base .. region.base + region.size).contains(&addr) {
+                progress_bar.set_position(scanned + (addr - region.base));
+                break;
+            }
+            scanned += region.size;
+        }
+    })
+        .inspect_err(|err| con::error_kv("scan failed", err))?;
+    progress_bar.finish_and_clear();
 
-    let mut matches_cc = Vec::new();
+    con::info_kv("scan completed", format!("{} match(es) on configured filler bytes", caves.len()));
 
-    let mut i = 0_usize;
-    while i < text.len() {
-        let mut byte = unsafe { *text.get_unchecked(i) };
-        let start_pos = i;
+    write_output(cli, &symbols, caves, || Digest {
+        input: format!("pid:{}", pid),
+        sections: Vec::new(),
+        caves: Vec::new(),
+    })
+}
 
-        match byte {
-            0xCC => {
-                while byte == 0xCC && i < text.len() {
-                    byte = unsafe { *text.get_unchecked(i) };
-                    i += 1;
-                }
+fn progress_bar_for(len: u64) -> ProgressBar {
+    let style = ProgressStyle::with_template(
+        "[{percent_precise}%] {bar:40.cyan/cyan} {pos:>7}/{len:7} {msg}"
+    ).unwrap().progress_chars("##-");
+    ProgressBar::new(len).with_style(style)
+}
 
-                let length = (i - start_pos) as u64;
-                progress_bar.inc(length);
+fn write_output(cli: &Cli, symbols: &SymbolTable, caves: Vec<Cave>, digest_shell: impl FnOnce() -> Digest) -> anyhow::Result<()> {
+    let mut output: Box<dyn Write> = match &cli.output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(stdout()),
+    };
 
-                if length >= cli.min_size {
-                    matches_cc.push((start_pos, length));
+    match cli.format.as_str() {
+        "json" => {
+            let mut digest = digest_shell();
+            digest.caves = caves;
+            serde_json::to_writer_pretty(&mut output, &digest)?;
+            writeln!(output)?;
+        },
+        "text" => {
+            for (i, cave) in caves.iter().enumerate() {
+                let annotation = annotate(symbols, cave.rva);
+                match cave.rva {
+                    Some(rva) => writeln!(output, "{}. [{}] at rva 0x{:x} (file offset 0x{:x}) length = {} filler = 0x{:02x}{}",
+                        i, cave.section, rva, cave.file_offset, cave.length, cave.filler_byte, annotation)?,
+                    None => writeln!(output, "{}. [{}] at file offset 0x{:x} length = {} filler = 0x{:02x}",
+                        i, cave.section, cave.file_offset, cave.length, cave.filler_byte)?,
                 }
-            },
-            _ => {
-                progress_bar.inc(1);
-                i += 1;
             }
-        }
+        },
+        other => anyhow::bail!("unsupported output format '{}'", other),
     }
 
-    progress_bar.finish_and_clear();
-    con::info_kv("scan completed", format!("{} match(es) on 0xcc", matches_cc.len()));
+    Ok(())
+}
 
-    let write_digest = |mut output: Box<dyn Write>| {
-        for (i, (start, length)) in matches_cc.iter().cloned().enumerate() {
-            // Currently `start` has offset form .exe start, but what we need is the rva.
-            let rva = start + section.virtual_address as usize;
-            writeln!(*output, "{}. at 0x{:x} length = {}", i, rva, length)?;
-        }
-        Ok::<(), anyhow::Error>(())
-    };
+fn build_symbol_table(cli: &Cli, executable: Option<&movile::format::Executable>) -> anyhow::Result<SymbolTable> {
+    if cli.no_symbols {
+        return Ok(SymbolTable::empty());
+    }
 
-    match &cli.output {
-        Some(path) => write_digest(Box::new(std::fs::File::create(path)?))?,
-        None => write_digest(Box::new(stdout()))?,
-    };
+    if let Some(path) = &cli.symbols {
+        return SymbolTable::from_map_file(path)
+            .inspect(|table| con::info_kv("loaded symbols from map file", table.len()))
+            .inspect_err(|err| con::error_kv("failed to load map file", err));
+    }
 
-    Ok(())
+    match executable {
+        Some(executable) => SymbolTable::from_executable(executable)
+            .inspect(|table| con::info_kv("loaded symbols from export table", table.len())),
+        None => Ok(SymbolTable::empty()),
+    }
 }
 
-fn find_text_section<'a>(executable: &'a PE) -> anyhow::Result<&'a SectionTable> {
-    for section in &executable.sections {
-        if section.name().ok().is_some_and(|name| name == ".text") {
-            return Ok(section);
-        }
+fn annotate(symbols: &SymbolTable, rva: Option<u64>) -> String {
+    match rva.and_then(|rva| symbols.nearest_preceding(rva)) {
+        Some((name, 0)) => format!(" ({})", name),
+        Some((name, offset)) => format!(" (after {}+0x{:x})", name, offset),
+        None => String::new(),
+    }
+}
+
+fn parse_patterns(patterns: &[String]) -> anyhow::Result<Vec<u8>> {
+    if patterns.is_empty() {
+        return Ok(vec![0xCC]);
     }
 
-    anyhow::bail!("failed to find .text")
+    patterns.iter()
+        .map(|pattern| u8::from_str_radix(pattern.trim_start_matches("0x"), 16)
+            .map_err(|err| anyhow::anyhow!("invalid pattern byte '{}': {}", pattern, err)))
+        .collect()
 }
 
 
@@ -0,0 +1,208 @@
+//! Reader for the executable memory of a running process.
+//!
+//! Static file scans miss code that only exists after runtime unpacking or
+//! relocation (packers, JIT stubs, self-modifying loaders). This module
+//! enumerates a target process's mapped executable regions and reads them
+//! directly out of its address space, so the cave scan can run over what is
+//! actually resident in memory rather than what shipped on disk. Only Linux
+//! is supported (`/proc/<pid>/maps` and `process_vm_readv`); on every other
+//! platform both functions return an error.
+
+/// One contiguous executable mapping inside a process's address space.
+#[derive(Clone, Debug)]
+pub struct ProcessRegion {
+    /// Owning module path (or a synthetic name for anonymous mappings).
+    pub module: String,
+    /// Base address of this mapping within the process.
+    pub base: u64,
+    /// Size of this mapping in bytes.
+    pub size: u64,
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::ProcessRegion;
+    use std::io::{BufRead, BufReader};
+
+    #[repr(C)]
+    struct IoVec {
+        base: *mut u8,
+        len: usize,
+    }
+
+    extern "C" {
+        fn process_vm_readv(
+            pid: i32,
+            local_iov: *const IoVec,
+            liovcnt: u64,
+            remote_iov: *const IoVec,
+            riovcnt: u64,
+            flags: u64,
+        ) -> isize;
+    }
+
+    pub fn enumerate_regions(pid: u32) -> anyhow::Result<Vec<ProcessRegion>> {
+        let maps_path = format!("/proc/{}/maps", pid);
+        let file = std::fs::File::open(&maps_path)
+            .map_err(|err| anyhow::anyhow!("failed to open {}: {}", maps_path, err))?;
+
+        let mut regions = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let (start, end, executable, module) = parse_maps_line(&line)
+                .ok_or_else(|| anyhow::anyhow!("malformed maps line: {}", line))?;
+
+            if !executable {
+                continue;
+            }
+
+            regions.push(ProcessRegion { module, base: start, size: end - start });
+        }
+
+        Ok(regions)
+    }
+
+    /// Parses one `/proc/<pid>/maps` line into `(start, end, executable, module)`.
+    ///
+    /// The five leading fields (`range`, `perms`, `offset`, `dev`, `inode`) are
+    /// always single-space separated; only the pathname column is padded for
+    /// alignment, and is absent entirely for anonymous mappings. So the first
+    /// five fields are pulled positionally and everything left over is trimmed
+    /// and treated as the pathname, falling back to `[anonymous]` only when
+    /// that trimmed remainder is empty. `executable` is true only for mappings
+    /// that are both readable and executable, matching what `read_region` can
+    /// actually fetch and what the scanner is meant to cover: execute-only
+    /// (`--xp`) mappings are excluded rather than handed to `process_vm_readv`
+    /// to fail on later.
+    fn parse_maps_line(line: &str) -> Option<(u64, u64, bool, String)> {
+        let mut fields = line.splitn(6, ' ');
+        let range = fields.next()?;
+        let perms = fields.next()?;
+        let _offset = fields.next()?;
+        let _dev = fields.next()?;
+        let _inode = fields.next()?;
+        let rest = fields.next().unwrap_or("").trim();
+
+        let (start_str, end_str) = range.split_once('-')?;
+        let start = u64::from_str_radix(start_str, 16).ok()?;
+        let end = u64::from_str_radix(end_str, 16).ok()?;
+        let executable = perms.contains('r') && perms.contains('x');
+
+        let module = if rest.is_empty() { "[anonymous]".to_string() } else { rest.to_string() };
+
+        Some((start, end, executable, module))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::parse_maps_line;
+
+        #[test]
+        fn parses_named_mapping() {
+            let line = "55a1b2c00000-55a1b2c05000 r-xp 00000000 08:01 123456                     /usr/bin/head";
+            let (start, end, executable, module) = parse_maps_line(line).unwrap();
+            assert_eq!(start, 0x55a1b2c00000);
+            assert_eq!(end, 0x55a1b2c05000);
+            assert!(executable);
+            assert_eq!(module, "/usr/bin/head");
+        }
+
+        #[test]
+        fn parses_anonymous_mapping_as_anonymous_not_inode() {
+            let line = "7f0a1c000000-7f0a1c021000 r-xp 00000000 00:00 0";
+            let (_, _, executable, module) = parse_maps_line(line).unwrap();
+            assert!(executable);
+            assert_eq!(module, "[anonymous]");
+        }
+
+        #[test]
+        fn non_executable_mapping_is_still_parsed() {
+            let line = "7f0a1c021000-7f0a1c022000 rw-p 00021000 08:01 123456 /usr/bin/head";
+            let (_, _, executable, _) = parse_maps_line(line).unwrap();
+            assert!(!executable);
+        }
+
+        #[test]
+        fn execute_only_mapping_is_not_flagged_executable() {
+            // Valid on hardened/XOM configurations; not readable, so not scannable.
+            let line = "7f0a1c023000-7f0a1c024000 --xp 00000000 00:00 0";
+            let (_, _, executable, _) = parse_maps_line(line).unwrap();
+            assert!(!executable);
+        }
+
+        #[test]
+        fn rejects_malformed_line() {
+            assert!(parse_maps_line("garbage").is_none());
+        }
+    }
+
+    pub fn read_region(pid: u32, region: &ProcessRegion) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = vec![0u8; region.size as usize];
+        let local = IoVec { base: buffer.as_mut_ptr(), len: buffer.len() };
+        let remote = IoVec { base: region.base as *mut u8, len: buffer.len() };
+
+        let read = unsafe { process_vm_readv(pid as i32, &local, 1, &remote, 1, 0) };
+        if read < 0 {
+            anyhow::bail!("process_vm_readv failed for {}: {}", region.module, std::io::Error::last_os_error());
+        }
+
+        buffer.truncate(read as usize);
+        Ok(buffer)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::ProcessRegion;
+
+    pub fn enumerate_regions(_pid: u32) -> anyhow::Result<Vec<ProcessRegion>> {
+        // TODO: walk the task's vm regions via `mach_vm_region` and resolve each
+        // mapping's owning image with `_dyld_process_info` or `proc_regionfilename`.
+        anyhow::bail!("process memory scanning is not yet implemented on macOS")
+    }
+
+    pub fn read_region(_pid: u32, _region: &ProcessRegion) -> anyhow::Result<Vec<u8>> {
+        // TODO: acquire a `task_t` via `task_for_pid` and read with `mach_vm_read`.
+        anyhow::bail!("process memory scanning is not yet implemented on macOS")
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use super::ProcessRegion;
+
+    pub fn enumerate_regions(_pid: u32) -> anyhow::Result<Vec<ProcessRegion>> {
+        // TODO: `OpenProcess` + `EnumProcessModulesEx` + `VirtualQueryEx` over each
+        // module's address range to find the executable pages.
+        anyhow::bail!("process memory scanning is not yet implemented on Windows")
+    }
+
+    pub fn read_region(_pid: u32, _region: &ProcessRegion) -> anyhow::Result<Vec<u8>> {
+        // TODO: `OpenProcess` + `ReadProcessMemory` into a local buffer.
+        anyhow::bail!("process memory scanning is not yet implemented on Windows")
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod imp {
+    use super::ProcessRegion;
+
+    pub fn enumerate_regions(_pid: u32) -> anyhow::Result<Vec<ProcessRegion>> {
+        anyhow::bail!("process memory scanning is not supported on this platform")
+    }
+
+    pub fn read_region(_pid: u32, _region: &ProcessRegion) -> anyhow::Result<Vec<u8>> {
+        anyhow::bail!("process memory scanning is not supported on this platform")
+    }
+}
+
+/// Enumerates the readable+executable regions mapped into `pid`'s address space.
+pub fn enumerate_regions(pid: u32) -> anyhow::Result<Vec<ProcessRegion>> {
+    imp::enumerate_regions(pid)
+}
+
+/// Reads `region` out of `pid`'s address space. The buffer is cut to the
+/// number of bytes actually copied, so it can be shorter than `region.size`.
+pub fn read_region(pid: u32, region: &ProcessRegion) -> anyhow::Result<Vec<u8>> {
+    imp::read_region(pid, region)
+}
@@ -0,0 +1,191 @@
+//! Nearest-preceding-symbol lookup for cave annotation.
+//!
+//! A cave reported as a bare RVA doesn't tell you which routine it trails.
+//! This module builds a sorted `(address, name)` table — either from the
+//! scanned image's own export table or from a companion `.map` file — and
+//! answers "what's the closest symbol at or before this address".
+
+use crate::format::Executable;
+
+/// A sorted table of symbol addresses, in the same address space as the
+/// `rva_base` reported for scan regions (RVA for PE, absolute VA for ELF/Mach-O).
+pub struct SymbolTable {
+    entries: Vec<(u64, String)>,
+}
+
+impl SymbolTable {
+    /// An empty table; `nearest_preceding` always returns `None`.
+    pub fn empty() -> Self {
+        SymbolTable { entries: Vec::new() }
+    }
+
+    /// Number of symbols loaded.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Builds a table from an MSVC linker `.map` file's "Publics by Value"
+    /// section, where symbol lines look like:
+    ///
+    /// ```text
+    ///  Address         Publics by Value              Rva+Base       Lib:Object
+    ///  0001:00001000   ?main@@YAHXZ                  00401000 f   i main.obj
+    /// ```
+    ///
+    /// The first column (`section:offset`) isn't a usable address on its own,
+    /// and the symbol name isn't the last whitespace-separated token (that's
+    /// the trailing `Lib:Object`), so lines are matched positionally: the
+    /// second column is the name, the third is the absolute `Rva+Base`
+    /// address. The base given by the file's `Preferred load address is ...`
+    /// line is subtracted from each address to yield an RVA; symbols below
+    /// that base are dropped. Lines that don't fit this shape (headers, blank
+    /// lines, section/group summaries) are skipped rather than erroring, since
+    /// `.map` files mix several unrelated tables together.
+    pub fn from_map_file(path: &std::path::Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("failed to read map file {}: {}", path.display(), err))?;
+
+        let mut load_address = 0;
+        let mut entries = Vec::new();
+        for line in contents.lines() {
+            if let Some(base) = line.trim().strip_prefix("Preferred load address is") {
+                load_address = u64::from_str_radix(base.trim(), 16).unwrap_or(0);
+                continue;
+            }
+
+            if let Some((address, name)) = parse_map_symbol_line(line) {
+                if let Some(rva) = address.checked_sub(load_address) {
+                    entries.push((rva, name));
+                }
+            }
+        }
+
+        entries.sort_by_key(|(address, _)| *address);
+        Ok(SymbolTable { entries })
+    }
+
+    /// Builds a table from the image's own export table (PE exports, ELF
+    /// symtab/dynsym, Mach-O symbol table).
+    pub fn from_executable(executable: &Executable) -> anyhow::Result<Self> {
+        let mut entries = Vec::new();
+
+        match executable {
+            Executable::Pe(pe) => {
+                for export in &pe.exports {
+                    if let Some(name) = export.name {
+                        entries.push((export.rva as u64, name.to_string()));
+                    }
+                }
+            },
+            Executable::Elf(elf) => {
+                let tables = [(&elf.syms, &elf.strtab), (&elf.dynsyms, &elf.dynstrtab)];
+                for (syms, strtab) in tables {
+                    for sym in syms.iter() {
+                        if sym.st_value == 0 || sym.st_name == 0 {
+                            continue;
+                        }
+
+                        if let Some(name) = strtab.get_at(sym.st_name) {
+                            entries.push((sym.st_value, name.to_string()));
+                        }
+                    }
+                }
+            },
+            Executable::MachO(macho) => {
+                for symbol in macho.symbols() {
+                    let (name, nlist) = symbol?;
+                    if nlist.n_value != 0 {
+                        entries.push((nlist.n_value, name.to_string()));
+                    }
+                }
+            },
+        }
+
+        entries.sort_by_key(|(address, _)| *address);
+        Ok(SymbolTable { entries })
+    }
+
+    /// Returns the name and offset of the nearest symbol at or before `address`.
+    pub fn nearest_preceding(&self, address: u64) -> Option<(&str, u64)> {
+        let idx = self.entries.partition_point(|(sym_address, _)| *sym_address <= address);
+        let (sym_address, name) = self.entries.get(idx.checked_sub(1)?)?;
+        Some((name.as_str(), address - sym_address))
+    }
+}
+
+/// Parses one line of an MSVC `.map` file's "Publics by Value" table into
+/// `(address, name)`, or `None` if the line doesn't have that shape (e.g. it's
+/// a header, a blank line, or belongs to one of the file's other tables).
+///
+/// Column 1 (`section:offset`) is only checked for the `:` that identifies a
+/// symbol row; column 3 (`Rva+Base`) is the address actually used, since
+/// column 1 is relative to a section the caller has no base for.
+fn parse_map_symbol_line(line: &str) -> Option<(u64, String)> {
+    let mut fields = line.split_whitespace();
+    let section_offset = fields.next()?;
+    let name = fields.next()?;
+    let rva_base = fields.next()?;
+
+    if !section_offset.contains(':') {
+        return None;
+    }
+
+    let address = u64::from_str_radix(rva_base, 16).ok()?;
+    Some((address, name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_realistic_msvc_map_line() {
+        let line = "0001:00001000       ?main@@YAHXZ              00401000 f   i main.obj";
+        let (address, name) = parse_map_symbol_line(line).unwrap();
+        assert_eq!(address, 0x401000);
+        assert_eq!(name, "?main@@YAHXZ");
+    }
+
+    #[test]
+    fn rejects_header_line_without_section_offset_column() {
+        let line = " Address         Publics by Value              Rva+Base       Lib:Object";
+        assert!(parse_map_symbol_line(line).is_none());
+    }
+
+    #[test]
+    fn rejects_blank_line() {
+        assert!(parse_map_symbol_line("").is_none());
+    }
+
+    fn table(entries: &[(u64, &str)]) -> SymbolTable {
+        SymbolTable { entries: entries.iter().map(|(addr, name)| (*addr, name.to_string())).collect() }
+    }
+
+    #[test]
+    fn nearest_preceding_on_empty_table_is_none() {
+        assert!(table(&[]).nearest_preceding(0x1000).is_none());
+    }
+
+    #[test]
+    fn nearest_preceding_before_first_symbol_is_none() {
+        assert!(table(&[(0x2000, "foo")]).nearest_preceding(0x1000).is_none());
+    }
+
+    #[test]
+    fn nearest_preceding_exact_match_has_zero_offset() {
+        let t = table(&[(0x1000, "foo"), (0x2000, "bar")]);
+        assert_eq!(t.nearest_preceding(0x2000), Some(("bar", 0)));
+    }
+
+    #[test]
+    fn nearest_preceding_picks_closest_earlier_symbol() {
+        let t = table(&[(0x1000, "foo"), (0x2000, "bar")]);
+        assert_eq!(t.nearest_preceding(0x2050), Some(("bar", 0x50)));
+    }
+
+    #[test]
+    fn nearest_preceding_with_duplicate_addresses_picks_last_inserted() {
+        let t = table(&[(0x1000, "foo"), (0x1000, "bar")]);
+        assert_eq!(t.nearest_preceding(0x1000), Some(("bar", 0)));
+    }
+}